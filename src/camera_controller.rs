@@ -0,0 +1,100 @@
+use glam::{Quat, Vec3};
+use winit::event::{ElementState, VirtualKeyCode};
+
+use crate::renderer::Camera;
+
+
+/// Persistent, keyboard/mouse-driven camera for the interactive viewer. WASD (plus Space/Ctrl
+/// for up/down) translate along the camera's own forward/right/up axes scaled by `speed` and
+/// delta-time, mouse motion turns yaw/pitch (pitch clamped so you can't flip over the top), and
+/// holding a shift key scales movement down by `slow_speed_factor`.
+pub struct CameraController {
+    pub camera: Camera,
+
+    pub speed: f32,
+    pub slow_speed_factor: f32,
+    pub mouse_sensitivity: f32,
+
+    yaw: f32,
+    pitch: f32,
+
+    move_forward: bool,
+    move_backward: bool,
+    move_left: bool,
+    move_right: bool,
+    move_up: bool,
+    move_down: bool,
+    slow: bool,
+}
+
+impl CameraController {
+    pub fn new(camera: Camera) -> Self {
+        CameraController {
+            camera,
+
+            speed: 5.0,
+            slow_speed_factor: 0.25,
+            mouse_sensitivity: 0.0025,
+
+            yaw: 0.0,
+            pitch: 0.0,
+
+            move_forward: false,
+            move_backward: false,
+            move_left: false,
+            move_right: false,
+            move_up: false,
+            move_down: false,
+            slow: false,
+        }
+    }
+
+    pub fn on_keyboard_input(&mut self, keycode: Option<VirtualKeyCode>, state: ElementState) {
+
+        let pressed = state == ElementState::Pressed;
+
+        match keycode {
+            Some(VirtualKeyCode::W) => self.move_forward = pressed,
+            Some(VirtualKeyCode::S) => self.move_backward = pressed,
+            Some(VirtualKeyCode::A) => self.move_left = pressed,
+            Some(VirtualKeyCode::D) => self.move_right = pressed,
+            Some(VirtualKeyCode::Space) => self.move_up = pressed,
+            Some(VirtualKeyCode::LControl) | Some(VirtualKeyCode::RControl) => self.move_down = pressed,
+            Some(VirtualKeyCode::LShift) | Some(VirtualKeyCode::RShift) => self.slow = pressed,
+            _ => (),
+        }
+    }
+
+    pub fn on_mouse_motion(&mut self, delta: (f64, f64)) {
+
+        self.yaw -= delta.0 as f32 * self.mouse_sensitivity;
+        self.pitch -= delta.1 as f32 * self.mouse_sensitivity;
+
+        let pitch_limit = 89.0_f32.to_radians();
+        self.pitch = self.pitch.clamp(-pitch_limit, pitch_limit);
+
+        self.camera.rotation = Quat::from_axis_angle(Vec3::Y, self.yaw) * Quat::from_axis_angle(Vec3::X, self.pitch);
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+
+        let forward = self.camera.rotation * Vec3::Z;
+        let right = self.camera.rotation * Vec3::X;
+        let up = self.camera.rotation * Vec3::Y;
+
+        let mut movement = Vec3::ZERO;
+
+        if self.move_forward { movement += forward; }
+        if self.move_backward { movement -= forward; }
+        if self.move_right { movement += right; }
+        if self.move_left { movement -= right; }
+        if self.move_up { movement += up; }
+        if self.move_down { movement -= up; }
+
+        if movement != Vec3::ZERO {
+            let speed = if self.slow { self.speed * self.slow_speed_factor } else { self.speed };
+
+            self.camera.position += movement.normalize() * speed * delta_time;
+        }
+    }
+}
@@ -2,12 +2,22 @@ use std::f32::consts::PI;
 
 use glam::Vec2;
 use renderer::{Scene, Camera, Sphere, Shape, SpotLight, Light, get_cursor_world_position, Material, DirectionalLight};
+use shapes::{RayMarched, Torus};
 use windowing::WindowingState;
-use winit::event::{WindowEvent, Event};
+use winit::event::{WindowEvent, Event, DeviceEvent, ElementState, VirtualKeyCode};
 use winit::event_loop::{ControlFlow, EventLoop};
 
 mod windowing;
 mod renderer;
+mod shapes;
+mod mesh;
+mod scene_format;
+mod camera_controller;
+
+
+/// Samples per pixel and max bounce used by the path-traced preview toggled with `Tab`.
+const PATH_TRACE_RAYS_PER_PIXEL: usize = 8;
+const PATH_TRACE_MAX_BOUNCE: usize = 5;
 
 
 fn main() {
@@ -15,11 +25,26 @@ fn main() {
     let event_loop = EventLoop::new();
 
     let dpi = 1;
-    let mut windowing_state = windowing::WindowingState::new(&event_loop, dpi);
+
+    let initial_camera = Camera {
+        position: glam::vec3(0.0, 0.0, 0.0),
+        rotation: glam::Quat::default(),
+        fov_y: 90.0 / 360.0 * PI,
+        near_z: 0.1,
+        aperture: 0.0,
+        focus_distance: 10.0,
+    };
+
+    let mut windowing_state = windowing::WindowingState::new(&event_loop, dpi, initial_camera);
     let mut cursor_position = Vec2::new(0.0, 0.0);
+    let mut path_traced = false;
+    let mut focused = false;
+
+    // Passing a path to a JSON scene document renders that scene instead of `simple_scene`.
+    let loaded_scene = std::env::args().nth(1).map(Scene::from_json_path);
 
     event_loop.run(move |event, _, control_flow| {
-        
+
         match event {
             Event::WindowEvent { event, .. } => match event {
 
@@ -33,35 +58,53 @@ fn main() {
                     windowing_state.resize(*new_inner_size);
                 },
 
+                WindowEvent::Focused(is_focused) => {
+                    focused = is_focused;
+                    windowing_state.set_captured(focused);
+                },
+
                 WindowEvent::CursorMoved { position, .. } => {
                     let logical_position = position.to_logical(dpi as f64);
                     cursor_position = glam::vec2(logical_position.x, logical_position.y);
                 },
-                /*
+
                 WindowEvent::KeyboardInput { input, ..} => {
-                    inputs.on_keyboard_input(input.virtual_keycode, input.state);
-                    engine.set_inputs(inputs.clone());
+
+                    if input.state == ElementState::Pressed && input.virtual_keycode == Some(VirtualKeyCode::Tab) {
+                        path_traced = !path_traced;
+                    }
+
+                    windowing_state.camera_controller.on_keyboard_input(input.virtual_keycode, input.state);
                 },
-                WindowEvent::MouseInput { state, button, ..} => {
-                    inputs.on_mouse_input(button, state);
-                    engine.set_inputs(inputs.clone());
-                },*/
 
                 _ => (),
             },
 
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => {
+                if focused {
+                    windowing_state.camera_controller.on_mouse_motion(delta);
+                }
+            },
+
             Event::MainEventsCleared => {
+                windowing_state.update_camera();
                 windowing_state.window.request_redraw();
             },
 
             Event::RedrawRequested(_) => {
 
-                let WindowingState { context, size, .. } = &mut windowing_state;
+                let WindowingState { context, size, camera_controller, .. } = &mut windowing_state;
                 let buffer = context.get_frame_mut();
 
-                let scene = simple_scene(cursor_position, Vec2::new(size.width as f32, size.height as f32));
+                let screen_size = glam::UVec2::new(size.width, size.height);
 
-                renderer::render(&scene, buffer, glam::UVec2::new(size.width, size.height));
+                match &loaded_scene {
+                    Some(scene) => render_scene(scene, buffer, screen_size, path_traced),
+                    None => {
+                        let scene = simple_scene(camera_controller.camera, cursor_position, Vec2::new(size.width as f32, size.height as f32));
+                        render_scene(&scene, buffer, screen_size, path_traced);
+                    }
+                }
 
                 windowing_state.render();
             }
@@ -72,23 +115,30 @@ fn main() {
 }
 
 
+/// Renders `scene` into `buffer`: the tile-threaded hard-shadow renderer by default, or the
+/// Monte Carlo path tracer while `path_traced` is toggled on (`Tab`).
+fn render_scene(scene: &Scene, buffer: &mut [u8], screen_size: glam::UVec2, path_traced: bool) {
+    if path_traced {
+        renderer::render_path_traced(scene, buffer, screen_size, PATH_TRACE_RAYS_PER_PIXEL, PATH_TRACE_MAX_BOUNCE);
+    }
+    else {
+        renderer::render_with_threads(scene, buffer, screen_size, renderer::THREAD_COUNT);
+    }
+}
 
-fn simple_scene(cursor_position: Vec2, screen_size: Vec2) -> Scene {
 
-    let camera = Camera { 
-        position: glam::vec3(0.0, 0.0, 0.0), 
-        rotation: glam::Quat::default(), 
-        fov_y: 90.0 / 360.0 * PI,
-        near_z: 0.1,
-    };
+fn simple_scene(camera: Camera, cursor_position: Vec2, screen_size: Vec2) -> Scene {
 
     let mut objects = Vec::new();
     let mut lights = Vec::new();
 
-    let blue_material = Material { color: glam::vec4(0.0, 0.4, 1.0, 1.0) };
-    let orange_material = Material { color: glam::vec4(1.0, 0.7, 0.2, 1.0) };
-    let green_material = Material { color: glam::vec4(0.4, 1.0, 0.6, 1.0) };
-    let white_material = Material { color: glam::vec4(1.0, 1.0, 1.0, 1.0) };
+    let blue_material = Material::Lambertian { albedo: glam::vec4(0.0, 0.4, 1.0, 1.0) };
+    let orange_material = Material::Lambertian { albedo: glam::vec4(1.0, 0.7, 0.2, 1.0) };
+    let green_material = Material::Lambertian { albedo: glam::vec4(0.4, 1.0, 0.6, 1.0) };
+    let white_material = Material::Lambertian { albedo: glam::vec4(1.0, 1.0, 1.0, 1.0) };
+    let metal_material = Material::Metal { albedo: glam::vec4(0.8, 0.8, 0.9, 1.0), fuzz: 0.05 };
+    let glass_material = Material::Dielectric { ior: 1.5 };
+    let light_material = Material::Emissive { radiant_exitance: glam::vec4(4.0, 4.0, 4.0, 1.0) };
 
 
     objects.push((0, Box::new(Sphere { position: glam::vec3(2.0, 2.0, 100.0), radius: 40.0 }) as Box<dyn Shape>, white_material));
@@ -102,6 +152,17 @@ fn simple_scene(cursor_position: Vec2, screen_size: Vec2) -> Scene {
     objects.push((5, Box::new(Sphere { position: glam::vec3(-3.0, -5.0, 15.0), radius: 0.7 }) as Box<dyn Shape>, green_material));
     objects.push((6, Box::new(Sphere { position: glam::vec3(-6.0, -3.0, 15.0), radius: 0.7 }) as Box<dyn Shape>, orange_material));
 
+    objects.push((7, Box::new(Sphere { position: glam::vec3(-1.0, -1.0, 8.0), radius: 0.5 }) as Box<dyn Shape>, metal_material));
+    objects.push((8, Box::new(Sphere { position: glam::vec3(1.0, -1.0, 7.0), radius: 0.5 }) as Box<dyn Shape>, glass_material));
+    objects.push((9, Box::new(Sphere { position: glam::vec3(0.0, 6.0, 12.0), radius: 2.0 }) as Box<dyn Shape>, light_material));
+
+    let mut mesh_triangles = mesh::load_obj("assets/pyramid.obj", orange_material, 10);
+    let next_entity_id = 10 + mesh_triangles.len() as u32;
+    objects.append(&mut mesh_triangles);
+
+    let torus = Torus { position: glam::vec3(3.0, 2.0, 11.0), major_radius: 0.8, minor_radius: 0.25 };
+    objects.push((next_entity_id, Box::new(RayMarched { sdf: torus }) as Box<dyn Shape>, green_material));
+
 
     let cursor_position = get_cursor_world_position(cursor_position, &camera, screen_size, 10.0);
     lights.push(Box::new(SpotLight { position: cursor_position, intensity: 1.0 }) as Box<dyn Light>);
@@ -111,9 +172,5 @@ fn simple_scene(cursor_position: Vec2, screen_size: Vec2) -> Scene {
     lights.push(Box::new(DirectionalLight { direction: glam::vec3(1.0, 0.5, 5.0), intensity: 1.0 }) as Box<dyn Light>);
 
 
-    Scene { 
-        objects, 
-        lights, 
-        camera,
-    }
+    Scene::new(objects, lights, camera, 3)
 }
\ No newline at end of file
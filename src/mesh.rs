@@ -0,0 +1,39 @@
+use crate::renderer::{Material, Shape};
+use crate::shapes::Triangle;
+
+
+/// Loads every triangle of every mesh in an OBJ file, all sharing `material`, and assigns
+/// each one a fresh entity id starting at `start_entity_id`. Per-face normals are computed
+/// from the winding order rather than read from the file.
+pub fn load_obj(path: &str, material: Material, start_entity_id: u32) -> Vec<(u32, Box<dyn Shape>, Material)> {
+
+    let load_options = tobj::LoadOptions { triangulate: true, ..Default::default() };
+    let (models, _) = tobj::load_obj(path, &load_options).expect("failed to load obj file");
+
+    let mut objects = Vec::new();
+    let mut entity_id = start_entity_id;
+
+    for model in models {
+
+        let positions = &model.mesh.positions;
+
+        let vertex = |index: u32| {
+            let i = index as usize * 3;
+            glam::vec3(positions[i], positions[i + 1], positions[i + 2])
+        };
+
+        for face in model.mesh.indices.chunks(3) {
+
+            let v0 = vertex(face[0]);
+            let v1 = vertex(face[1]);
+            let v2 = vertex(face[2]);
+
+            let normal = (v1 - v0).cross(v2 - v0).normalize();
+
+            objects.push((entity_id, Box::new(Triangle { v0, v1, v2, normal }) as Box<dyn Shape>, material));
+            entity_id += 1;
+        }
+    }
+
+    objects
+}
@@ -1,10 +1,11 @@
 use std::f32::consts::PI;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use glam::{UVec2, Vec3, Quat, Vec2, Vec4};
 
 
 
-pub trait Light {
+pub trait Light: Send + Sync {
     fn get_light_ray(&self, point: Vec3, normal: Vec3) -> Option<Ray>;
     fn check_shadow(&self, point: Vec3, intersected_point: Vec3) -> bool;
     fn intensity(&self, point: Vec3, normal: Vec3) -> f32;
@@ -70,51 +71,11 @@ impl Light for DirectionalLight {
 
 
 
-pub trait Shape {
-    fn intersect(&self, ray: &Ray) -> Option<f32>;
-    fn get_normal(&self, point: Vec3) -> Vec3;
-}
-
-
-pub struct Sphere {
-    pub position: Vec3,
-    pub radius: f32,
-}
-
-impl Shape for Sphere {
-    fn intersect(&self, ray: &Ray) -> Option<f32> {
-        
-        let a = ray.direction.powf(2.0);
-        let b = 2.0 * ray.direction * (ray.position - self.position);
-        let c = ray.position.powf(2.0) + self.position.powf(2.0) - 2.0 * ray.position * self.position;
-
-        let a = a.x + a.y + a.z;
-        let b = b.x + b.y + b.z;
-        let c = c.x + c.y + c.z - self.radius.powf(2.0);
-
-
-        let partial_t = b.powf(2.0) - 4.0 * a * c;
-
-        if partial_t < 0.0 {
-            None
-        }
-        else {
-            let partial_t_sqrt = partial_t.sqrt();
-
-            let t1 = (-b + partial_t_sqrt) / 2.0 * a;
-            let t2 = (-b - partial_t_sqrt) / 2.0 * a;
-
-            let t = t1.min(t2);
-            Some(t)
-        }
-    }
-
-    fn get_normal(&self, point: Vec3) -> Vec3 {
-        (point - self.position).normalize()
-    }
-}
+pub use crate::shapes::{Shape, Sphere, Triangle};
+use crate::shapes::Bvh;
 
 
+#[derive(Clone, Copy)]
 pub struct Camera {
     pub position: Vec3,
     pub rotation: Quat,
@@ -122,12 +83,18 @@ pub struct Camera {
     ///fov_y in radians
     pub fov_y: f32,
     pub near_z: f32,
+
+    /// Lens radius. `0.0` is a pinhole camera (perfectly sharp); larger values blur
+    /// anything away from `focus_distance` for a depth-of-field effect.
+    pub aperture: f32,
+    /// Distance from the camera at which the image is in perfect focus.
+    pub focus_distance: f32,
 }
 
 
 pub fn get_cursor_world_position(cursor_position: Vec2, camera: &Camera, screen_size: Vec2, z_depth: f32) -> Vec3 {
 
-    let Camera { position, rotation, fov_y, near_z } = *camera;
+    let Camera { position, rotation, fov_y, near_z, .. } = *camera;
 
     let scale = fov_y.atan() * near_z;
     let scale = glam::vec3(scale, scale, 1.0);
@@ -151,8 +118,144 @@ pub fn get_cursor_world_position(cursor_position: Vec2, camera: &Camera, screen_
 
 
 #[derive(Clone, Copy)]
-pub struct Material {
-    pub color: Vec4,
+pub enum Material {
+    Lambertian { albedo: Vec4 },
+    Metal { albedo: Vec4, fuzz: f32 },
+    Dielectric { ior: f32 },
+    /// A surface that emits light rather than scattering it, with `radiant_exitance` as
+    /// its emitted radiance. Used as the light source for [`render_path_traced`].
+    Emissive { radiant_exitance: Vec4 },
+}
+
+impl Material {
+
+    fn albedo(&self) -> Vec4 {
+        match *self {
+            Material::Lambertian { albedo } => albedo,
+            Material::Metal { albedo, .. } => albedo,
+            Material::Dielectric { .. } => Vec4::ONE,
+            Material::Emissive { .. } => Vec4::ZERO,
+        }
+    }
+
+    fn emission(&self) -> Vec4 {
+        match *self {
+            Material::Emissive { radiant_exitance } => radiant_exitance,
+            _ => Vec4::ZERO,
+        }
+    }
+
+    /// Computes the ray this material scatters `ray_direction` into after hitting `point`
+    /// with surface normal `normal`. `ray_direction` is assumed to already point towards the surface.
+    fn scatter(&self, ray_direction: Vec3, normal: Vec3, point: Vec3) -> Ray {
+        match *self {
+            Material::Lambertian { .. } => {
+                let direction = normal + random_unit_vector();
+                Ray { position: point, direction: direction.normalize() }
+            }
+
+            Material::Metal { fuzz, .. } => {
+                let reflected = ray_direction - 2.0 * ray_direction.dot(normal) * normal;
+                let direction = reflected.normalize() + fuzz * random_in_unit_sphere();
+
+                Ray { position: point, direction: direction.normalize() }
+            }
+
+            Material::Dielectric { ior } => {
+                let front_face = ray_direction.dot(normal) < 0.0;
+                let (normal, ior_ratio) = if front_face { (normal, 1.0 / ior) } else { (-normal, ior) };
+
+                let unit_direction = ray_direction.normalize();
+                let cos_theta = (-unit_direction).dot(normal).min(1.0);
+                let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+                let cannot_refract = ior_ratio * sin_theta > 1.0;
+
+                let direction = if cannot_refract || schlick_reflectance(cos_theta, ior_ratio) > rand::random::<f32>() {
+                    unit_direction - 2.0 * unit_direction.dot(normal) * normal
+                }
+                else {
+                    let r_perp = ior_ratio * (unit_direction + cos_theta * normal);
+                    let r_par = -(1.0 - r_perp.length_squared()).abs().sqrt() * normal;
+
+                    r_perp + r_par
+                };
+
+                Ray { position: point, direction: direction.normalize() }
+            }
+
+            Material::Emissive { .. } => Ray { position: point, direction: normal },
+        }
+    }
+}
+
+
+/// Schlick's approximation for the probability that a dielectric surface reflects
+/// (rather than refracts) a ray hitting at `cos_theta`, given `ior_ratio = ior_from / ior_to`.
+fn schlick_reflectance(cos_theta: f32, ior_ratio: f32) -> f32 {
+    let r0 = (1.0 - ior_ratio) / (1.0 + ior_ratio);
+    let r0 = r0 * r0;
+
+    r0 + (1.0 - r0) * (1.0 - cos_theta).powf(5.0)
+}
+
+
+fn random_in_unit_sphere() -> Vec3 {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+
+    loop {
+        let p = glam::vec3(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+
+        if p.length_squared() < 1.0 {
+            return p;
+        }
+    }
+}
+
+fn random_unit_vector() -> Vec3 {
+    random_in_unit_sphere().normalize()
+}
+
+
+fn random_in_unit_disk() -> Vec2 {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+
+    loop {
+        let p = glam::vec2(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+
+        if p.length_squared() < 1.0 {
+            return p;
+        }
+    }
+}
+
+
+/// Builds the ray through `pixel_position` (in the camera's local near-plane space,
+/// mapped into world space here by `camera_matrix`). When `camera.aperture` is `0.0`
+/// this is a plain pinhole ray; otherwise the origin is jittered over the lens disk and
+/// aimed back at the point on the focus plane, producing depth-of-field blur.
+fn camera_ray(camera: &Camera, camera_matrix: glam::Mat4, pixel_position: Vec3) -> Ray {
+
+    let Camera { position, rotation, aperture, focus_distance, .. } = *camera;
+
+    let pixel_world_position = camera_matrix.project_point3(pixel_position);
+    let direction = (pixel_world_position - position).normalize();
+
+    if aperture <= 0.0 {
+        return Ray { position: pixel_world_position, direction };
+    }
+
+    let focus_point = pixel_world_position + direction * focus_distance;
+
+    let right = rotation * Vec3::X;
+    let up = rotation * Vec3::Y;
+    let lens_offset = random_in_unit_disk() * aperture;
+
+    let origin = pixel_world_position + right * lens_offset.x + up * lens_offset.y;
+
+    Ray { position: origin, direction: (focus_point - origin).normalize() }
 }
 
 
@@ -160,17 +263,30 @@ pub struct Scene {
     pub objects: Vec<(u32, Box<dyn Shape>, Material)>,
     pub lights: Vec<Box<dyn Light>>,
     pub camera: Camera,
+    pub max_depth: usize,
+
+    bvh: Bvh,
+}
+
+impl Scene {
+    /// Builds the scene's BVH over `objects` once up front so `intersect_ray` can traverse
+    /// it instead of scanning every object on every ray.
+    pub fn new(objects: Vec<(u32, Box<dyn Shape>, Material)>, lights: Vec<Box<dyn Light>>, camera: Camera, max_depth: usize) -> Self {
+        let bvh = Bvh::build(&objects);
+
+        Scene { objects, lights, camera, max_depth, bvh }
+    }
 }
 
 
 #[derive(Debug)]
 pub struct Ray {
-    position: Vec3,
-    direction: Vec3,
+    pub(crate) position: Vec3,
+    pub(crate) direction: Vec3,
 }
 
 impl Ray {
-    fn get_point(&self, t: f32) -> Vec3 {
+    pub(crate) fn get_point(&self, t: f32) -> Vec3 {
         self.position + self.direction * t
     }
 }
@@ -178,7 +294,7 @@ impl Ray {
 
 pub fn render(scene: &Scene, buffer: &mut [u8], screen_size: UVec2) {
 
-    let Camera { position, rotation, fov_y, near_z } = scene.camera;
+    let Camera { position, rotation, fov_y, near_z, .. } = scene.camera;
 
     let scale = fov_y.atan() * near_z;
     let scale = glam::vec3(scale, scale, 1.0);
@@ -194,12 +310,8 @@ pub fn render(scene: &Scene, buffer: &mut [u8], screen_size: UVec2) {
         for x in 0..screen_size.x {
 
             let pixel_position = glam::vec3(x as f32 - screen_size_f32.x / 2.0, (y as f32 - screen_size_f32.y / 2.0) * -1.0, near_z) / glam::vec3(screen_size_f32.y, screen_size_f32.y, 1.0);
-            let pixel_world_position = camera_matrix.project_point3(pixel_position);
 
-            rays.push(Ray { 
-                position: pixel_world_position,
-                direction: (pixel_world_position - position).normalize(),
-            });
+            rays.push(camera_ray(&scene.camera, camera_matrix, pixel_position));
         }
     }
 
@@ -207,7 +319,7 @@ pub fn render(scene: &Scene, buffer: &mut [u8], screen_size: UVec2) {
 
         let i = i * 4;
 
-        let color = trace_ray(scene, ray, 3) * 255.0;
+        let color = trace_ray(scene, ray, scene.max_depth) * 255.0;
 
         buffer[i + 0] = color.x as u8;
         buffer[i + 1] = color.y as u8;
@@ -217,6 +329,196 @@ pub fn render(scene: &Scene, buffer: &mut [u8], screen_size: UVec2) {
 }
 
 
+/// Default number of worker threads for [`render_with_threads`] when the caller
+/// doesn't have a better number to hand in (e.g. from `std::thread::available_parallelism`).
+pub const THREAD_COUNT: usize = 8;
+
+
+/// Tile-based variant of [`render`] that splits `buffer` into at most `thread_count` contiguous
+/// row bands of `ceil(height / thread_count)` rows each and traces each band on its own scoped thread, so no two threads ever touch
+/// the same pixels. A background thread periodically logs overall progress by polling
+/// an `AtomicUsize` that every worker bumps after finishing a row.
+pub fn render_with_threads(scene: &Scene, buffer: &mut [u8], screen_size: UVec2, thread_count: usize) {
+
+    let Camera { position, rotation, fov_y, near_z, .. } = scene.camera;
+
+    let scale = fov_y.atan() * near_z;
+    let scale = glam::vec3(scale, scale, 1.0);
+
+    let camera_matrix = glam::Mat4::from_scale_rotation_translation(scale, rotation, position);
+    let screen_size_f32 = glam::vec2(screen_size.x as f32, screen_size.y as f32);
+
+    let row_stride = screen_size.x as usize * 4;
+    let rows_per_band = (screen_size.y as usize + thread_count - 1) / thread_count;
+
+    let total_pixels = (screen_size.x * screen_size.y) as usize;
+    let completed = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+
+        for (band_index, band) in buffer.chunks_mut(rows_per_band * row_stride).enumerate() {
+
+            let completed = &completed;
+
+            scope.spawn(move || {
+
+                let first_row = band_index * rows_per_band;
+                let band_rows = band.len() / row_stride;
+
+                for row_offset in 0..band_rows {
+
+                    let y = (first_row + row_offset) as u32;
+
+                    for x in 0..screen_size.x {
+
+                        let pixel_position = glam::vec3(x as f32 - screen_size_f32.x / 2.0, (y as f32 - screen_size_f32.y / 2.0) * -1.0, near_z) / glam::vec3(screen_size_f32.y, screen_size_f32.y, 1.0);
+                        let ray = camera_ray(&scene.camera, camera_matrix, pixel_position);
+
+                        let color = trace_ray(scene, ray, scene.max_depth) * 255.0;
+
+                        let i = (row_offset * screen_size.x as usize + x as usize) * 4;
+                        band[i + 0] = color.x as u8;
+                        band[i + 1] = color.y as u8;
+                        band[i + 2] = color.z as u8;
+                        band[i + 3] = color.w as u8;
+                    }
+
+                    completed.fetch_add(screen_size.x as usize, Ordering::Relaxed);
+                }
+            });
+        }
+
+        scope.spawn(|| log_render_progress(&completed, total_pixels));
+    });
+}
+
+
+/// Polls `completed` until every pixel has been traced, printing the overall percentage
+/// as it goes. Runs on its own thread so it doesn't slow down the render workers.
+fn log_render_progress(completed: &AtomicUsize, total_pixels: usize) {
+
+    loop {
+        let done = completed.load(Ordering::Relaxed);
+        let percent = done as f32 / total_pixels as f32 * 100.0;
+
+        println!("rendering... {percent:.1}%");
+
+        if done >= total_pixels {
+            break;
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
+
+
+/// Monte Carlo path-traced alternative to [`render`]/[`trace_ray`]. Instead of sampling
+/// explicit `Light`s with hard shadows, radiance is accumulated by recursively bouncing
+/// rays off diffuse surfaces with cosine-weighted hemisphere sampling until a
+/// [`Material::Emissive`] surface (or the sky) is hit. `rays_per_pixel` samples are
+/// averaged per pixel to reduce the resulting noise.
+pub fn render_path_traced(scene: &Scene, buffer: &mut [u8], screen_size: UVec2, rays_per_pixel: usize, max_bounce: usize) {
+
+    let Camera { position, rotation, fov_y, near_z, .. } = scene.camera;
+
+    let scale = fov_y.atan() * near_z;
+    let scale = glam::vec3(scale, scale, 1.0);
+
+    let camera_matrix = glam::Mat4::from_scale_rotation_translation(scale, rotation, position);
+    let screen_size_f32 = glam::vec2(screen_size.x as f32, screen_size.y as f32);
+
+    for y in 0..screen_size.y {
+        for x in 0..screen_size.x {
+
+            let pixel_position = glam::vec3(x as f32 - screen_size_f32.x / 2.0, (y as f32 - screen_size_f32.y / 2.0) * -1.0, near_z) / glam::vec3(screen_size_f32.y, screen_size_f32.y, 1.0);
+
+            let mut accumulated = Vec4::ZERO;
+
+            for _ in 0..rays_per_pixel {
+                let ray = camera_ray(&scene.camera, camera_matrix, pixel_position);
+
+                accumulated += trace_path(scene, ray, max_bounce);
+            }
+
+            let color = (accumulated / rays_per_pixel as f32 * 255.0).min(glam::vec4(255.0, 255.0, 255.0, 255.0));
+
+            let i = ((y * screen_size.x + x) * 4) as usize;
+            buffer[i + 0] = color.x as u8;
+            buffer[i + 1] = color.y as u8;
+            buffer[i + 2] = color.z as u8;
+            buffer[i + 3] = color.w as u8;
+        }
+    }
+}
+
+
+fn trace_path(scene: &Scene, ray: Ray, max_bounce: usize) -> Vec4 {
+
+    if max_bounce == 0 {
+        return Vec4::ZERO;
+    }
+
+    if let Some((t, _entity_id, shape, material)) = intersect_ray(scene, &ray, None) {
+
+        let point = ray.get_point(t);
+        let normal = shape.get_normal(point);
+        let emission = material.emission();
+
+        if let Material::Emissive { .. } = material {
+            return emission;
+        }
+
+        // Russian roulette: terminate low-contribution paths early, compensating
+        // surviving ones by dividing by the survival probability to stay unbiased.
+        let albedo = material.albedo();
+        let survival = albedo.max_element().clamp(0.05, 1.0);
+
+        if rand::random::<f32>() > survival {
+            return emission;
+        }
+
+        let scattered = match material {
+            Material::Lambertian { .. } => Ray { position: point, direction: cosine_weighted_hemisphere(normal) },
+            _ => material.scatter(ray.direction, normal, point),
+        };
+
+        emission + albedo * trace_path(scene, scattered, max_bounce - 1) / survival
+    }
+    else {
+        Vec4::ZERO
+    }
+}
+
+
+/// Samples a direction over the hemisphere around `normal` with probability proportional
+/// to the cosine of the angle to `normal`, so the cosine term and pdf cancel out of the
+/// rendering equation and the caller can just do `emission + albedo * trace(scattered)`.
+fn cosine_weighted_hemisphere(normal: Vec3) -> Vec3 {
+    use rand::Rng;
+    let mut rng = rand::thread_rng();
+
+    let r1: f32 = rng.gen();
+    let r2: f32 = rng.gen();
+
+    let phi = 2.0 * PI * r1;
+    let x = phi.cos() * r2.sqrt();
+    let y = phi.sin() * r2.sqrt();
+    let z = (1.0 - r2).sqrt();
+
+    let (tangent, bitangent) = build_tangent_frame(normal);
+
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
+
+fn build_tangent_frame(normal: Vec3) -> (Vec3, Vec3) {
+    let helper = if normal.x.abs() > 0.9 { Vec3::Y } else { Vec3::X };
+    let tangent = helper.cross(normal).normalize();
+    let bitangent = normal.cross(tangent);
+
+    (tangent, bitangent)
+}
+
+
 fn trace_ray(scene: &Scene, ray: Ray, max_bounce: usize) -> Vec4 {
 
     if let Some((t, entity_id, shape, material)) = intersect_ray(scene, &ray, None) {
@@ -239,20 +541,22 @@ fn trace_ray(scene: &Scene, ray: Ray, max_bounce: usize) -> Vec4 {
 
 
                 if has_light {
-                    Some(light.intensity(point, normal) * material.color)
+                    Some(light.intensity(point, normal) * material.albedo())
                 }
                 else { None }
             }
             else { None }
-            
+
         }).sum::<Vec4>();
 
 
-        if max_bounce != 0 {
-            let direction = ray.direction - 2.0 * ray.direction.dot(normal) * normal;
-            let ray = Ray { position: point, direction };
+        // Diffuse surfaces are already lit directly above; recursing their random scatter direction
+        // here would turn this deterministic renderer into a noisy one-sample path tracer (that's
+        // what `render_path_traced` is for). Only specular materials bounce an extra ray.
+        if max_bounce != 0 && !matches!(material, Material::Lambertian { .. }) {
+            let scattered = material.scatter(ray.direction, normal, point);
 
-            intensity += trace_ray(scene, ray, max_bounce - 1) * 0.5;
+            intensity += trace_ray(scene, scattered, max_bounce - 1) * material.albedo() * 0.5;
         }
 
         
@@ -264,18 +568,8 @@ fn trace_ray(scene: &Scene, ray: Ray, max_bounce: usize) -> Vec4 {
 
 fn intersect_ray<'a>(scene: &'a Scene, ray: &Ray, exclude_id: Option<u32>) -> Option<(f32, u32, &'a Box<dyn Shape>, &'a Material)> {
 
-    scene.objects.iter().filter_map(|(entity_id, shape, material)| {
-
-        if let Some(exclude_id) = exclude_id {
-            if exclude_id == *entity_id { return None }
-        }
-
-        if let Some(t) = shape.intersect(ray) {
-
-            if t >= 0.0 { Some((t, *entity_id, shape, material)) }
-            else { None }
-        }
-        else { None }
-
-    }).min_by(|(a, _, _, _), (b, _, _, _)| a.total_cmp(b))
+    scene.bvh.intersect(ray, &scene.objects, exclude_id).map(|(t, object_index)| {
+        let (entity_id, shape, material) = &scene.objects[object_index];
+        (t, *entity_id, shape, material)
+    })
 }
\ No newline at end of file
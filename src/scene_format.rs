@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::Path;
+
+use glam::{Quat, Vec3, Vec4};
+use serde::Deserialize;
+
+use crate::mesh;
+use crate::renderer::{Camera, DirectionalLight, Light, Material, Scene, Shape, SpotLight, Sphere};
+
+
+#[derive(Deserialize)]
+struct SceneDocument {
+    max_depth: usize,
+    camera: CameraDocument,
+    objects: Vec<ObjectDocument>,
+    lights: Vec<LightDocument>,
+}
+
+#[derive(Deserialize)]
+struct CameraDocument {
+    position: [f32; 3],
+    look_at: [f32; 3],
+    up: [f32; 3],
+    fov_y: f32,
+    #[serde(default = "default_near_z")]
+    near_z: f32,
+    #[serde(default)]
+    aperture: f32,
+    #[serde(default)]
+    focus_distance: f32,
+}
+
+fn default_near_z() -> f32 {
+    0.1
+}
+
+#[derive(Deserialize)]
+struct ObjectDocument {
+    shape: ShapeDocument,
+    material: MaterialDocument,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum ShapeDocument {
+    Sphere { position: [f32; 3], radius: f32 },
+    Mesh { path: String },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum MaterialDocument {
+    Lambertian { albedo: [f32; 4] },
+    Metal { albedo: [f32; 4], fuzz: f32 },
+    Dielectric { ior: f32 },
+    Emissive { radiant_exitance: [f32; 4] },
+}
+
+impl From<MaterialDocument> for Material {
+    fn from(document: MaterialDocument) -> Self {
+        match document {
+            MaterialDocument::Lambertian { albedo } => Material::Lambertian { albedo: to_vec4(albedo) },
+            MaterialDocument::Metal { albedo, fuzz } => Material::Metal { albedo: to_vec4(albedo), fuzz },
+            MaterialDocument::Dielectric { ior } => Material::Dielectric { ior },
+            MaterialDocument::Emissive { radiant_exitance } => Material::Emissive { radiant_exitance: to_vec4(radiant_exitance) },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum LightDocument {
+    Spot { position: [f32; 3], intensity: f32 },
+    Directional { direction: [f32; 3], intensity: f32 },
+}
+
+impl From<LightDocument> for Box<dyn Light> {
+    fn from(document: LightDocument) -> Self {
+        match document {
+            LightDocument::Spot { position, intensity } => Box::new(SpotLight { position: to_vec3(position), intensity }),
+            LightDocument::Directional { direction, intensity } => Box::new(DirectionalLight { direction: to_vec3(direction), intensity }),
+        }
+    }
+}
+
+fn to_vec3(value: [f32; 3]) -> Vec3 {
+    glam::vec3(value[0], value[1], value[2])
+}
+
+fn to_vec4(value: [f32; 4]) -> Vec4 {
+    glam::vec4(value[0], value[1], value[2], value[3])
+}
+
+fn look_rotation(forward: Vec3, up: Vec3) -> Quat {
+    let right = up.cross(forward).normalize();
+    let up = forward.cross(right);
+
+    Quat::from_mat3(&glam::Mat3::from_cols(right, up, forward))
+}
+
+
+impl Scene {
+    /// Parses a JSON scene document (camera, objects and lights) from `path` into a
+    /// renderable `Scene`, resolving each object into a `Box<dyn Shape>` and assigning
+    /// entity ids automatically.
+    pub fn from_json_path(path: impl AsRef<Path>) -> Scene {
+
+        let text = fs::read_to_string(path).expect("failed to read scene file");
+        let document: SceneDocument = serde_json::from_str(&text).expect("failed to parse scene file");
+
+        let camera = Camera {
+            position: to_vec3(document.camera.position),
+            rotation: look_rotation((to_vec3(document.camera.look_at) - to_vec3(document.camera.position)).normalize(), to_vec3(document.camera.up)),
+            fov_y: document.camera.fov_y,
+            near_z: document.camera.near_z,
+            aperture: document.camera.aperture,
+            focus_distance: document.camera.focus_distance,
+        };
+
+        let mut objects = Vec::new();
+        let mut next_entity_id = 0;
+
+        for object in document.objects {
+
+            let material = Material::from(object.material);
+
+            match object.shape {
+                ShapeDocument::Sphere { position, radius } => {
+                    objects.push((next_entity_id, Box::new(Sphere { position: to_vec3(position), radius }) as Box<dyn Shape>, material));
+                    next_entity_id += 1;
+                }
+
+                ShapeDocument::Mesh { path } => {
+                    let mut triangles = mesh::load_obj(&path, material, next_entity_id);
+                    next_entity_id += triangles.len() as u32;
+                    objects.append(&mut triangles);
+                }
+            }
+        }
+
+        let lights = document.lights.into_iter().map(|light| Box::<dyn Light>::from(light)).collect();
+
+        Scene::new(objects, lights, camera, document.max_depth)
+    }
+}
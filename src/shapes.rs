@@ -0,0 +1,458 @@
+use glam::{Vec2, Vec3};
+
+use crate::renderer::{Material, Ray};
+
+
+
+pub trait Shape: Send + Sync {
+    fn intersect(&self, ray: &Ray) -> Option<f32>;
+    fn get_normal(&self, point: Vec3) -> Vec3;
+    fn bounding_box(&self) -> Aabb;
+}
+
+
+pub struct Sphere {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+impl Shape for Sphere {
+    fn intersect(&self, ray: &Ray) -> Option<f32> {
+
+        let a = ray.direction.powf(2.0);
+        let b = 2.0 * ray.direction * (ray.position - self.position);
+        let c = ray.position.powf(2.0) + self.position.powf(2.0) - 2.0 * ray.position * self.position;
+
+        let a = a.x + a.y + a.z;
+        let b = b.x + b.y + b.z;
+        let c = c.x + c.y + c.z - self.radius.powf(2.0);
+
+
+        let partial_t = b.powf(2.0) - 4.0 * a * c;
+
+        if partial_t < 0.0 {
+            None
+        }
+        else {
+            let partial_t_sqrt = partial_t.sqrt();
+
+            let t1 = (-b + partial_t_sqrt) / 2.0 * a;
+            let t2 = (-b - partial_t_sqrt) / 2.0 * a;
+
+            // For a ray starting on the sphere's surface (e.g. a dielectric's refracted
+            // continuation ray) the smaller root is ~0, not negative, so a plain `>= 0.0`
+            // check would keep re-hitting the entry point instead of the true exit. Ignore
+            // roots within EPSILON of the origin and take the closest one still ahead of it.
+            const EPSILON: f32 = 1e-4;
+
+            let t_near = t1.min(t2);
+            let t_far = t1.max(t2);
+
+            if t_near > EPSILON {
+                Some(t_near)
+            }
+            else if t_far > EPSILON {
+                Some(t_far)
+            }
+            else {
+                None
+            }
+        }
+    }
+
+    fn get_normal(&self, point: Vec3) -> Vec3 {
+        (point - self.position).normalize()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let radius = glam::vec3(self.radius, self.radius, self.radius);
+
+        Aabb { min: self.position - radius, max: self.position + radius }
+    }
+}
+
+
+/// A single triangle face, typically produced in bulk by [`crate::mesh::load_obj`].
+pub struct Triangle {
+    pub v0: Vec3,
+    pub v1: Vec3,
+    pub v2: Vec3,
+    pub normal: Vec3,
+}
+
+impl Shape for Triangle {
+    /// Möller–Trumbore ray/triangle intersection.
+    fn intersect(&self, ray: &Ray) -> Option<f32> {
+
+        const EPSILON: f32 = 1e-6;
+
+        let e1 = self.v1 - self.v0;
+        let e2 = self.v2 - self.v0;
+
+        let p = ray.direction.cross(e2);
+        let det = e1.dot(p);
+
+        if det.abs() < EPSILON {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let tvec = ray.position - self.v0;
+
+        let u = tvec.dot(p) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let q = tvec.cross(e1);
+        let v = ray.direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = e2.dot(q) * inv_det;
+        if t < EPSILON {
+            return None;
+        }
+
+        Some(t)
+    }
+
+    fn get_normal(&self, _point: Vec3) -> Vec3 {
+        self.normal
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb {
+            min: self.v0.min(self.v1).min(self.v2),
+            max: self.v0.max(self.v1).max(self.v2),
+        }
+    }
+}
+
+
+/// A signed distance function: negative inside the surface, positive outside, zero on it.
+/// `distance` must never overstep the true distance, or sphere tracing can step past the surface.
+pub trait Sdf: Send + Sync {
+    fn distance(&self, point: Vec3) -> f32;
+    fn bounding_box(&self) -> Aabb;
+}
+
+const SPHERE_TRACE_EPSILON: f32 = 1e-4;
+const SPHERE_TRACE_MAX_DIST: f32 = 1000.0;
+const SPHERE_TRACE_MAX_STEPS: u32 = 128;
+const NORMAL_EPSILON: f32 = 1e-3;
+
+/// Renders any [`Sdf`] as a [`Shape`] by sphere tracing: step the ray forward by the signed
+/// distance at the current point, repeating until that distance falls below
+/// `SPHERE_TRACE_EPSILON` (hit) or the ray has gone past `SPHERE_TRACE_MAX_DIST` /
+/// `SPHERE_TRACE_MAX_STEPS` (miss).
+pub struct RayMarched<S: Sdf> {
+    pub sdf: S,
+}
+
+impl<S: Sdf> Shape for RayMarched<S> {
+    fn intersect(&self, ray: &Ray) -> Option<f32> {
+
+        let mut t = 0.0;
+
+        for _ in 0..SPHERE_TRACE_MAX_STEPS {
+
+            let distance = self.sdf.distance(ray.get_point(t));
+
+            if distance < SPHERE_TRACE_EPSILON {
+                return Some(t);
+            }
+
+            t += distance;
+
+            if t > SPHERE_TRACE_MAX_DIST {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    /// Estimates the surface normal as the gradient of the distance field, via central
+    /// differences along each axis.
+    fn get_normal(&self, point: Vec3) -> Vec3 {
+
+        let dx = glam::vec3(NORMAL_EPSILON, 0.0, 0.0);
+        let dy = glam::vec3(0.0, NORMAL_EPSILON, 0.0);
+        let dz = glam::vec3(0.0, 0.0, NORMAL_EPSILON);
+
+        let gradient = glam::vec3(
+            self.sdf.distance(point + dx) - self.sdf.distance(point - dx),
+            self.sdf.distance(point + dy) - self.sdf.distance(point - dy),
+            self.sdf.distance(point + dz) - self.sdf.distance(point - dz),
+        );
+
+        gradient.normalize()
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.sdf.bounding_box()
+    }
+}
+
+
+/// A box with its edges rounded off by `radius`.
+pub struct RoundBox {
+    pub position: Vec3,
+    pub half_extents: Vec3,
+    pub radius: f32,
+}
+
+impl Sdf for RoundBox {
+    fn distance(&self, point: Vec3) -> f32 {
+        let q = (point - self.position).abs() - self.half_extents;
+        q.max(Vec3::ZERO).length() + q.max_element().min(0.0) - self.radius
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let bound = self.half_extents + Vec3::splat(self.radius);
+        Aabb { min: self.position - bound, max: self.position + bound }
+    }
+}
+
+
+/// A torus lying in the local XZ plane, with `major_radius` the distance from its center to
+/// the tube's core and `minor_radius` the tube's thickness.
+pub struct Torus {
+    pub position: Vec3,
+    pub major_radius: f32,
+    pub minor_radius: f32,
+}
+
+impl Sdf for Torus {
+    fn distance(&self, point: Vec3) -> f32 {
+        let local = point - self.position;
+        let q = Vec2::new(Vec2::new(local.x, local.z).length() - self.major_radius, local.y);
+        q.length() - self.minor_radius
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        let bound = glam::vec3(outer, self.minor_radius, outer);
+        Aabb { min: self.position - bound, max: self.position + bound }
+    }
+}
+
+
+/// CSG union: the shape occupied by `a` or `b`.
+pub struct Union<A: Sdf, B: Sdf> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Union<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.a.distance(point).min(self.b.distance(point))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.a.bounding_box().union(&self.b.bounding_box())
+    }
+}
+
+
+/// CSG intersection: the shape occupied by both `a` and `b`.
+pub struct Intersection<A: Sdf, B: Sdf> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Intersection<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.a.distance(point).max(self.b.distance(point))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        let a = self.a.bounding_box();
+        let b = self.b.bounding_box();
+
+        Aabb { min: a.min.max(b.min), max: a.max.min(b.max) }
+    }
+}
+
+
+/// CSG subtraction: the shape occupied by `a` with `b` carved out of it.
+pub struct Subtraction<A: Sdf, B: Sdf> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for Subtraction<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+        self.a.distance(point).max(-self.b.distance(point))
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.a.bounding_box()
+    }
+}
+
+
+/// Like [`Union`], but rounds the seam between `a` and `b` into a smooth blend instead of a
+/// hard edge, by `smoothness` (larger values blend over a wider region).
+pub struct SmoothUnion<A: Sdf, B: Sdf> {
+    pub a: A,
+    pub b: B,
+    pub smoothness: f32,
+}
+
+impl<A: Sdf, B: Sdf> Sdf for SmoothUnion<A, B> {
+    fn distance(&self, point: Vec3) -> f32 {
+
+        let a = self.a.distance(point);
+        let b = self.b.distance(point);
+
+        let h = (0.5 + 0.5 * (b - a) / self.smoothness).clamp(0.0, 1.0);
+        let blend = b + (a - b) * h;
+
+        blend - self.smoothness * h * (1.0 - h)
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.a.bounding_box().union(&self.b.bounding_box())
+    }
+}
+
+
+/// Axis-aligned bounding box used to cull [`Bvh`] subtrees a ray can't possibly hit.
+#[derive(Clone, Copy)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Slab test: true if `ray` passes through this box at all (the hit point isn't computed,
+    /// the BVH traversal only needs a cheap reject).
+    fn intersects(&self, ray: &Ray) -> bool {
+
+        let inv_dir = 1.0 / ray.direction;
+
+        let t0 = (self.min - ray.position) * inv_dir;
+        let t1 = (self.max - ray.position) * inv_dir;
+
+        let t_min = t0.min(t1).max_element();
+        let t_max = t0.max(t1).min_element();
+
+        t_max >= t_min.max(0.0)
+    }
+}
+
+
+enum BvhNode {
+    Leaf { object_index: usize, bounds: Aabb },
+    Interior { left: Box<BvhNode>, right: Box<BvhNode>, bounds: Aabb },
+}
+
+impl BvhNode {
+    fn bounds(&self) -> Aabb {
+        match self {
+            BvhNode::Leaf { bounds, .. } => *bounds,
+            BvhNode::Interior { bounds, .. } => *bounds,
+        }
+    }
+}
+
+
+/// Axis-aligned bounding volume hierarchy over a scene's objects, built once and traversed
+/// on every ray so `intersect_ray` doesn't have to scan every object linearly.
+pub struct Bvh {
+    root: Option<BvhNode>,
+}
+
+impl Bvh {
+    pub fn build(objects: &[(u32, Box<dyn Shape>, Material)]) -> Self {
+
+        let leaves = objects.iter().enumerate()
+            .map(|(object_index, (_, shape, _))| BvhNode::Leaf { object_index, bounds: shape.bounding_box() })
+            .collect();
+
+        Bvh { root: Self::build_recursive(leaves) }
+    }
+
+    /// Recursively splits `nodes` along the longest axis of their combined bounds at the
+    /// median centroid, until each leaf is alone.
+    fn build_recursive(mut nodes: Vec<BvhNode>) -> Option<BvhNode> {
+
+        if nodes.is_empty() {
+            return None;
+        }
+
+        if nodes.len() == 1 {
+            return nodes.pop();
+        }
+
+        let bounds = nodes.iter().map(BvhNode::bounds).reduce(|a, b| a.union(&b)).unwrap();
+        let extent = bounds.max - bounds.min;
+
+        if extent.x >= extent.y && extent.x >= extent.z {
+            nodes.sort_by(|a, b| a.bounds().centroid().x.total_cmp(&b.bounds().centroid().x));
+        }
+        else if extent.y >= extent.z {
+            nodes.sort_by(|a, b| a.bounds().centroid().y.total_cmp(&b.bounds().centroid().y));
+        }
+        else {
+            nodes.sort_by(|a, b| a.bounds().centroid().z.total_cmp(&b.bounds().centroid().z));
+        }
+
+        let right_nodes = nodes.split_off(nodes.len() / 2);
+
+        let left = Self::build_recursive(nodes).unwrap();
+        let right = Self::build_recursive(right_nodes).unwrap();
+        let bounds = left.bounds().union(&right.bounds());
+
+        Some(BvhNode::Interior { left: Box::new(left), right: Box::new(right), bounds })
+    }
+
+    /// Returns the closest hit, as `(t, object_index)`, skipping `exclude_id`.
+    pub fn intersect(&self, ray: &Ray, objects: &[(u32, Box<dyn Shape>, Material)], exclude_id: Option<u32>) -> Option<(f32, usize)> {
+        self.root.as_ref().and_then(|root| Self::intersect_node(root, ray, objects, exclude_id))
+    }
+
+    fn intersect_node(node: &BvhNode, ray: &Ray, objects: &[(u32, Box<dyn Shape>, Material)], exclude_id: Option<u32>) -> Option<(f32, usize)> {
+
+        if !node.bounds().intersects(ray) {
+            return None;
+        }
+
+        match node {
+            BvhNode::Leaf { object_index, .. } => {
+
+                let (entity_id, shape, _) = &objects[*object_index];
+
+                if Some(*entity_id) == exclude_id {
+                    return None;
+                }
+
+                shape.intersect(ray).filter(|t| *t >= 0.0).map(|t| (t, *object_index))
+            }
+
+            BvhNode::Interior { left, right, .. } => {
+
+                let hit_left = Self::intersect_node(left, ray, objects, exclude_id);
+                let hit_right = Self::intersect_node(right, ray, objects, exclude_id);
+
+                match (hit_left, hit_right) {
+                    (Some(left), Some(right)) => Some(if left.0 <= right.0 { left } else { right }),
+                    (Some(left), None) => Some(left),
+                    (None, Some(right)) => Some(right),
+                    (None, None) => None,
+                }
+            }
+        }
+    }
+}
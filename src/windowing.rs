@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use winit::dpi::{LogicalSize, PhysicalSize};
 use winit::window::{WindowBuilder, Window};
 
@@ -6,6 +8,9 @@ use winit::event_loop::EventLoop;
 use pixels::{SurfaceTexture, PixelsBuilder};
 use pixels::wgpu::{PowerPreference, RequestAdapterOptions, Color};
 
+use crate::camera_controller::CameraController;
+use crate::renderer::Camera;
+
 
 
 pub struct WindowingState {
@@ -14,10 +19,13 @@ pub struct WindowingState {
 
     pub context: pixels::Pixels,
     pub size: LogicalSize<u32>,
+
+    pub camera_controller: CameraController,
+    last_update: Instant,
 }
 
 impl WindowingState {
-    pub fn new<T>(event_loop: &EventLoop<T>, dpi: u32) -> Self {
+    pub fn new<T>(event_loop: &EventLoop<T>, dpi: u32, initial_camera: Camera) -> Self {
 
         let window = WindowBuilder::new()
             .with_maximized(false)
@@ -51,20 +59,40 @@ impl WindowingState {
 
             context,
             size: LogicalSize::default(),
+
+            camera_controller: CameraController::new(initial_camera),
+            last_update: Instant::now(),
         };
 
         renderer.resize(surface_size);
 
         renderer
     }
-    
-    
+
+
     pub fn render(&self) {
-        
+
         self.context.render().unwrap();
         self.window.request_redraw();
     }
 
+    /// Grabs and hides the OS cursor while the window is focused, so the free-fly camera reads
+    /// mouse motion instead of the cursor wandering off over the viewport; releases it on focus loss.
+    pub fn set_captured(&self, captured: bool) {
+        let _ = self.window.set_cursor_grab(captured);
+        self.window.set_cursor_visible(!captured);
+    }
+
+    /// Advances the camera controller by the time elapsed since the last call.
+    pub fn update_camera(&mut self) {
+
+        let now = Instant::now();
+        let delta_time = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+
+        self.camera_controller.update(delta_time);
+    }
+
     pub fn resize(&mut self, size: PhysicalSize<u32>) {
 
         self.context.resize_surface(size.width, size.height);